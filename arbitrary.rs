@@ -2,7 +2,10 @@
 
 
 use super::std;
-use super::std::rand::{Rand, RngUtil};
+use super::std::rand::Rand;
+use super::lazy::Lazy;
+use super::gen::Gen;
+use super::unstructured::Unstructured;
 
 use std::cell::Cell;
 use std::hashmap::{HashMap, HashSet};
@@ -10,22 +13,53 @@ use std::hashmap::{HashMap, HashSet};
 
 /**
  The Arbitrary trait can generate a randomly chosen value (with restrictions).
- You can pass a size factor to allow specifying test size (sizes of vectors and
- numbers).
+ You pass a `Gen` -- an RNG bundled with a size factor -- to allow specifying
+ test size (sizes of vectors and numbers) and to make runs reproducible.
  */
 pub trait Arbitrary {
     /**
      arbitrary should return an arbitrary value of its type.
-     The value should be randomly chosen and its size should be scaled by the size
-     parameter.
+     The value should be randomly chosen from `g` and its size should be
+     scaled by `g.size()`.
      */
-    fn arbitrary(uint) -> Self;
+    fn arbitrary(g: &mut Gen) -> Self;
+
+    /**
+     arbitrary_from should deterministically build a value of its type by
+     consuming bytes from `u`, rather than sampling randomness. `u` is
+     allowed to run out of bytes, in which case the remainder of the value
+     is read as zero, so every byte string maps to some value of `Self`.
+
+     This lets a coverage-guided fuzzer drive generation directly: mutating
+     the raw bytes fed to `arbitrary_from` mutates the resulting structured
+     value.
+     */
+    fn arbitrary_from(u: &mut Unstructured) -> Self;
+
+    /**
+     shrink should return a lazy list of values that are in some sense smaller
+     than `self`. The default implementation returns an empty list, so `self`
+     is reported as-is when no better shrink exists.
+
+     The list must never contain `self`, and every candidate must be strictly
+     smaller than `self`, so that repeatedly shrinking a value is guaranteed
+     to terminate (excluding `self` alone still allows two values to shrink
+     into each other). See `check::check` for the driver that
+     calls `shrink` on a failing value to minimize it.
+     */
+    fn shrink(&self) -> Lazy<Self> { Lazy::new() }
 }
 
 /// Create an arbitrary value of type T
 #[inline]
-pub fn arbitrary<T: Arbitrary>(sz: uint) -> T {
-    Arbitrary::arbitrary(sz)
+pub fn arbitrary<T: Arbitrary>(g: &mut Gen) -> T {
+    Arbitrary::arbitrary(g)
+}
+
+/// Create a value of type T by consuming bytes from `u`
+#[inline]
+pub fn arbitrary_from<T: Arbitrary>(u: &mut Unstructured) -> T {
+    Arbitrary::arbitrary_from(u)
 }
 
 /// A wrapper type to reuse an existing Rand instance for the Arbitrary impl
@@ -36,28 +70,50 @@ pub struct Random<T>(T);
 #[deriving(Eq, Clone)]
 pub struct SmallN(uint);
 
-fn small_n(size: uint) -> uint {
-    let f: std::rand::distributions::Exp1 = std::rand::random();
+fn small_n(g: &mut Gen) -> uint {
+    let size = g.size();
+    let f: std::rand::distributions::Exp1 = g.gen();
     let n = ((*f) * (size as f64)) as uint;
     n.min(&(16 * size))
 }
 
+/// There is no notion of "size" in a byte buffer, so lengths read from an
+/// `Unstructured` are clamped to this many elements.
+static MAX_UNSTRUCTURED_LEN: uint = 32;
+
+fn small_n_from(u: &mut Unstructured) -> uint {
+    u.int_in_range(0, MAX_UNSTRUCTURED_LEN)
+}
+
+/// Consume `nbytes` (at most 8) from `u`, most significant byte first.
+fn read_width(u: &mut Unstructured, nbytes: uint) -> u64 {
+    let mut buf = [0u8, ..8];
+    u.fill(buf.mut_slice_to(nbytes));
+    let mut x: u64 = 0;
+    let mut i = 0;
+    while i < nbytes {
+        x = (x << 8) | (buf[i] as u64);
+        i += 1;
+    }
+    x
+}
+
 /* Helper: Iter */
-#[deriving(Clone)]
-struct Iter<T> {
+struct Iter<'a, T> {
+    gen: &'a mut Gen,
     count: uint,
-    size: uint,
 }
 
-fn arbiter<T>(sz: uint) -> Iter<T> {
-    Iter{count: small_n(sz), size: sz }
+fn arbiter<'a, T>(g: &'a mut Gen) -> Iter<'a, T> {
+    let n = small_n(g);
+    Iter{gen: g, count: n}
 }
 
-impl<T: Arbitrary> Iterator<T> for Iter<T> {
+impl<'a, T: Arbitrary> Iterator<T> for Iter<'a, T> {
     fn next(&mut self) -> Option<T> {
         if self.count > 0 {
             self.count -= 1;
-            Some(arbitrary(self.size))
+            Some(arbitrary(self.gen))
         } else { None }
     }
 
@@ -66,11 +122,26 @@ impl<T: Arbitrary> Iterator<T> for Iter<T> {
     }
 }
 
+/// Read `n` elements of `T` from `u`, where `n` is itself read from `u`.
+fn vec_from<T: Arbitrary>(u: &mut Unstructured) -> ~[T] {
+    let n = small_n_from(u);
+    let mut v = ~[];
+    let mut i = 0;
+    while i < n {
+        v.push(arbitrary_from(u));
+        i += 1;
+    }
+    v
+}
 
 macro_rules! arb_rand( ($T:ty) => (
         impl Arbitrary for $T {
-            fn arbitrary(_: uint) -> $T {
-                std::rand::random()
+            fn arbitrary(g: &mut Gen) -> $T {
+                g.gen()
+            }
+
+            fn arbitrary_from(u: &mut Unstructured) -> $T {
+                read_width(u, 8) as $T
             }
         }
     )
@@ -78,20 +149,82 @@ macro_rules! arb_rand( ($T:ty) => (
 
 macro_rules! arb_tuple( ($($T:ident),+ ) => (
         impl<$($T: Arbitrary),+> Arbitrary for ($($T),+) {
-            fn arbitrary(sz: uint) -> ($($T),+) {
-                ($(arbitrary::<$T>(sz)),+)
+            fn arbitrary(g: &mut Gen) -> ($($T),+) {
+                ($(arbitrary::<$T>(g)),+)
+            }
+
+            fn arbitrary_from(u: &mut Unstructured) -> ($($T),+) {
+                ($(arbitrary_from::<$T>(u)),+)
             }
         }
     )
 )
 
-arb_rand!(i8)
-//arb_rand!(u8)
-arb_rand!(int)
-arb_rand!(uint)
+/* Shrink an integer towards zero: 0 first, then x - y for y = x, x/2, x/4, ...
+ * until y == 0. Signed types additionally emit -x. */
+macro_rules! arb_int_unsigned( ($T:ty, $w:expr) => (
+        impl Arbitrary for $T {
+            fn arbitrary(g: &mut Gen) -> $T {
+                g.gen()
+            }
+
+            fn arbitrary_from(u: &mut Unstructured) -> $T {
+                read_width(u, $w) as $T
+            }
+
+            fn shrink(&self) -> Lazy<$T> {
+                let x = *self;
+                do Lazy::create |L| {
+                    if x != 0 {
+                        let mut y = x;
+                        while y != 0 {
+                            L.push(x - y);
+                            y /= 2;
+                        }
+                    }
+                }
+            }
+        }
+    )
+)
+
+macro_rules! arb_int_signed( ($T:ty, $w:expr, $min:expr) => (
+        impl Arbitrary for $T {
+            fn arbitrary(g: &mut Gen) -> $T {
+                g.gen()
+            }
+
+            fn arbitrary_from(u: &mut Unstructured) -> $T {
+                read_width(u, $w) as $T
+            }
+
+            fn shrink(&self) -> Lazy<$T> {
+                let x = *self;
+                do Lazy::create |L| {
+                    if x != 0 {
+                        let mut y = x;
+                        while y != 0 {
+                            L.push(x - y);
+                            y /= 2;
+                        }
+                        /* -min_value() overflows back to itself in two's
+                         * complement, which would violate "shrink never
+                         * emits self"; skip the negation in that case. */
+                        if x < 0 && x != $min {
+                            L.push(-x);
+                        }
+                    }
+                }
+            }
+        }
+    )
+)
+
+arb_int_signed!(i8, 1, std::i8::min_value())
+arb_int_unsigned!(u8, 1)
+arb_int_signed!(int, 8, std::int::min_value())
+arb_int_unsigned!(uint, 8)
 arb_rand!(float)
-arb_rand!(bool)
-arb_rand!(())
 
 arb_tuple!(A, B)
 arb_tuple!(A, B, C)
@@ -101,94 +234,339 @@ arb_tuple!(A, B, C, D, E, F)
 arb_tuple!(A, B, C, D, E, F, G)
 arb_tuple!(A, B, C, D, E, F, G, H)
 
+impl Arbitrary for bool {
+    fn arbitrary(g: &mut Gen) -> bool {
+        g.gen()
+    }
+
+    fn arbitrary_from(u: &mut Unstructured) -> bool {
+        u.int_in_range(0, 1) == 1
+    }
+}
+
+impl Arbitrary for () {
+    fn arbitrary(_: &mut Gen) -> () { () }
+
+    fn arbitrary_from(_: &mut Unstructured) -> () { () }
+}
+
 impl<T: Rand> Arbitrary for Random<T> {
-    fn arbitrary(_: uint) -> Random<T> {
-        Random(std::rand::random())
+    fn arbitrary(g: &mut Gen) -> Random<T> {
+        Random(g.gen())
+    }
+
+    fn arbitrary_from(u: &mut Unstructured) -> Random<T> {
+        let seed = read_width(u, 8);
+        let mut g = Gen::new(seed, 0);
+        Random(g.gen())
     }
 }
 
 impl<T: Arbitrary> Arbitrary for ~T {
     #[inline]
-    fn arbitrary(sz: uint) -> ~T { ~arbitrary(sz) }
+    fn arbitrary(g: &mut Gen) -> ~T { ~arbitrary(g) }
+
+    #[inline]
+    fn arbitrary_from(u: &mut Unstructured) -> ~T { ~arbitrary_from(u) }
+
+    fn shrink(&self) -> Lazy<~T> {
+        let inner = (**self).shrink();
+        do Lazy::create |L| {
+            do L.push_map(inner) |s| { ~s }
+        }
+    }
 }
 
 impl<T: 'static + Arbitrary> Arbitrary for @T {
     #[inline]
-    fn arbitrary(sz: uint) -> @T { @arbitrary(sz) }
+    fn arbitrary(g: &mut Gen) -> @T { @arbitrary(g) }
+
+    #[inline]
+    fn arbitrary_from(u: &mut Unstructured) -> @T { @arbitrary_from(u) }
 }
 
 impl<T: 'static + Arbitrary> Arbitrary for @mut T {
     #[inline]
-    fn arbitrary(sz: uint) -> @mut T { @mut arbitrary(sz) }
+    fn arbitrary(g: &mut Gen) -> @mut T { @mut arbitrary(g) }
+
+    #[inline]
+    fn arbitrary_from(u: &mut Unstructured) -> @mut T { @mut arbitrary_from(u) }
 }
 
-impl Arbitrary for u8 {
-    fn arbitrary(_: uint) -> u8 {
-        std::rand::random()
-    }
+/* The surrogate range 0xD800..0xDFFF is not a valid scalar value, so it is
+ * excluded from the space of code points a char can be drawn from. */
+static SURROGATE_LO: u32 = 0xD800;
+static SURROGATE_HI: u32 = 0xDFFF;
+static SURROGATE_LEN: u32 = SURROGATE_HI - SURROGATE_LO + 1;
+static MAX_CODEPOINT: u32 = 0x10FFFF;
+
+/// Map `n` in `[0, MAX_CODEPOINT - SURROGATE_LEN]` onto a valid code point,
+/// skipping over the surrogate range.
+fn codepoint_from_index(n: u32) -> char {
+    let n = if n < SURROGATE_LO { n } else { n + SURROGATE_LEN };
+    std::char::from_u32(n).unwrap_or(' ')
+}
+
+/// Coerce a raw `u32` that may fall inside the surrogate range into the
+/// nearest valid code point below it.
+fn valid_codepoint(n: u32) -> char {
+    let n = if n >= SURROGATE_LO && n <= SURROGATE_HI { SURROGATE_LO - 1 } else { n };
+    std::char::from_u32(n).unwrap_or(' ')
 }
 
 impl Arbitrary for char {
-    fn arbitrary(_: uint) -> char {
-        std::rand::random::<u8>() as char
+    fn arbitrary(g: &mut Gen) -> char {
+        // Bias the distribution so ASCII and Latin-1 show up often, with
+        // BMP and astral-plane code points getting a smaller share.
+        let n: uint = g.gen_range(0u, 100);
+        let code = if n < 50 {
+            g.gen_range(0x20u32, 0x7Fu32)
+        } else if n < 70 {
+            g.gen_range(0x7Fu32, 0x100u32)
+        } else if n < 90 {
+            g.gen_range(0x100u32, SURROGATE_LO - SURROGATE_LEN)
+        } else {
+            g.gen_range(SURROGATE_LO - SURROGATE_LEN, MAX_CODEPOINT - SURROGATE_LEN + 1)
+        };
+        codepoint_from_index(code)
+    }
+
+    fn arbitrary_from(u: &mut Unstructured) -> char {
+        let n = u.int_in_range(0, (MAX_CODEPOINT - SURROGATE_LEN) as uint);
+        codepoint_from_index(n as u32)
+    }
+
+    fn shrink(&self) -> Lazy<char> {
+        let c = *self;
+        do Lazy::create |L| {
+            for &cand in ['a', ' ', '0'].iter() {
+                if (cand as u32) < (c as u32) {
+                    L.push(cand);
+                }
+            }
+            let x = c as u32;
+            if x != 0 {
+                let mut y = x;
+                while y != 0 {
+                    let cand = x - y;
+                    if cand != x {
+                        L.push(valid_codepoint(cand));
+                    }
+                    y /= 2;
+                }
+            }
+        }
     }
 }
 
 impl Arbitrary for SmallN {
-    fn arbitrary(sz: uint) -> SmallN {
-        SmallN(small_n(sz))
+    fn arbitrary(g: &mut Gen) -> SmallN {
+        SmallN(small_n(g))
+    }
+
+    fn arbitrary_from(u: &mut Unstructured) -> SmallN {
+        SmallN(small_n_from(u))
     }
 }
 
-impl<T: Arbitrary> Arbitrary for ~[T] {
-    fn arbitrary(sz: uint) -> ~[T] {
-        arbiter::<T>(sz).collect()
+/* `Clone` is needed only by `shrink`, which slices `self` and rebuilds
+ * owned vectors from the pieces (`to_owned`/`clone` require it);
+ * `arbitrary`/`arbitrary_from` don't use it. This crate has no precedent
+ * for a bound on a single trait method narrower than the impl, so it's
+ * taken on the whole impl -- this does narrow `~[T]: Arbitrary` to
+ * `Clone` element types relative to the baseline, which is accepted as
+ * the cost of adding `shrink`. */
+impl<T: Arbitrary + Clone> Arbitrary for ~[T] {
+    fn arbitrary(g: &mut Gen) -> ~[T] {
+        arbiter::<T>(g).collect()
+    }
+
+    fn arbitrary_from(u: &mut Unstructured) -> ~[T] {
+        vec_from(u)
+    }
+
+    fn shrink(&self) -> Lazy<~[T]> {
+        let v = self.clone();
+        do Lazy::create |L| {
+            let n = v.len();
+            /* n/2 would be 0 for n == 1, which would skip chunk removal
+             * entirely and make the empty vector unreachable; fall back to
+             * removing the whole (single) element in that case. */
+            let mut k = if n > 1 { n / 2 } else { n };
+            while k > 0 {
+                let mut i = 0;
+                while i + k <= n {
+                    let mut shrunk = v.slice_to(i).to_owned();
+                    shrunk.push_all(v.slice(i + k, n));
+                    L.push(shrunk);
+                    i += k;
+                }
+                k /= 2;
+            }
+
+            let mut i = 0;
+            while i < n {
+                let before = v.slice_to(i).to_owned();
+                let after = v.slice(i + 1, n).to_owned();
+                do L.push_map(v[i].shrink()) |s| {
+                    let mut shrunk = before.clone();
+                    shrunk.push(s);
+                    shrunk.push_all(after);
+                    shrunk
+                }
+                i += 1;
+            }
+        }
     }
 }
 
 impl<T: Arbitrary> Arbitrary for Option<T> {
-    fn arbitrary(sz: uint) -> Option<T> {
-        if std::rand::random() {
-            Some(arbitrary(sz))
+    fn arbitrary(g: &mut Gen) -> Option<T> {
+        if g.gen() {
+            Some(arbitrary(g))
+        } else {
+            None
+        }
+    }
+
+    fn arbitrary_from(u: &mut Unstructured) -> Option<T> {
+        if u.int_in_range(0, 1) == 1 {
+            Some(arbitrary_from(u))
         } else {
             None
         }
     }
 
+    fn shrink(&self) -> Lazy<Option<T>> {
+        match *self {
+            None => Lazy::new(),
+            Some(ref x) => {
+                let inner = x.shrink();
+                do Lazy::create |L| {
+                    L.push(None);
+                    do L.push_map(inner) |s| { Some(s) }
+                }
+            }
+        }
+    }
 }
 
 impl<T: Arbitrary, U: Arbitrary> Arbitrary for Result<T, U> {
-    fn arbitrary(sz: uint) -> Result<T, U> {
-        if std::rand::random() {
-            Ok(arbitrary(sz))
+    fn arbitrary(g: &mut Gen) -> Result<T, U> {
+        if g.gen() {
+            Ok(arbitrary(g))
+        } else {
+            Err(arbitrary(g))
+        }
+    }
+
+    fn arbitrary_from(u: &mut Unstructured) -> Result<T, U> {
+        if u.int_in_range(0, 1) == 1 {
+            Ok(arbitrary_from(u))
         } else {
-            Err(arbitrary(sz))
+            Err(arbitrary_from(u))
+        }
+    }
+
+    fn shrink(&self) -> Lazy<Result<T, U>> {
+        match *self {
+            Ok(ref x) => {
+                let inner = x.shrink();
+                do Lazy::create |L| {
+                    do L.push_map(inner) |s| { Ok(s) }
+                }
+            }
+            Err(ref x) => {
+                let inner = x.shrink();
+                do Lazy::create |L| {
+                    do L.push_map(inner) |s| { Err(s) }
+                }
+            }
         }
     }
 }
 
 impl<T: Arbitrary, U: Arbitrary> Arbitrary for Either<T, U> {
-    fn arbitrary(sz: uint) -> Either<T, U> {
-        if std::rand::random() {
-            Left(arbitrary(sz))
+    fn arbitrary(g: &mut Gen) -> Either<T, U> {
+        if g.gen() {
+            Left(arbitrary(g))
+        } else {
+            Right(arbitrary(g))
+        }
+    }
+
+    fn arbitrary_from(u: &mut Unstructured) -> Either<T, U> {
+        if u.int_in_range(0, 1) == 1 {
+            Left(arbitrary_from(u))
         } else {
-            Right(arbitrary(sz))
+            Right(arbitrary_from(u))
+        }
+    }
+
+    fn shrink(&self) -> Lazy<Either<T, U>> {
+        match *self {
+            Left(ref x) => {
+                let inner = x.shrink();
+                do Lazy::create |L| {
+                    do L.push_map(inner) |s| { Left(s) }
+                }
+            }
+            Right(ref x) => {
+                let inner = x.shrink();
+                do Lazy::create |L| {
+                    do L.push_map(inner) |s| { Right(s) }
+                }
+            }
         }
     }
 }
 
 impl Arbitrary for ~str {
-    fn arbitrary(sz: uint) -> ~str {
-        let rng = &mut *std::rand::task_rng();
-        let n = small_n(sz);
-        rng.gen_str(n)
+    fn arbitrary(g: &mut Gen) -> ~str {
+        let n = small_n(g);
+        let mut s = ~"";
+        let mut i = 0;
+        while i < n {
+            s.push_char(arbitrary(g));
+            i += 1;
+        }
+        s
+    }
+
+    fn arbitrary_from(u: &mut Unstructured) -> ~str {
+        let n = small_n_from(u);
+        let mut s = ~"";
+        let mut i = 0;
+        while i < n {
+            s.push_char(arbitrary_from(u));
+            i += 1;
+        }
+        s
+    }
+
+    fn shrink(&self) -> Lazy<~str> {
+        let chars: ~[char] = self.iter().collect();
+        let inner = chars.shrink();
+        do Lazy::create |L| {
+            do L.push_map(inner) |cs: ~[char]| {
+                cs.iter().collect()
+            }
+        }
     }
 }
 
 impl <T: Arbitrary> Arbitrary for Cell<T> {
-    fn arbitrary(sz: uint) -> Cell<T> {
-        if std::rand::random() {
-            Cell::new(arbitrary(sz))
+    fn arbitrary(g: &mut Gen) -> Cell<T> {
+        if g.gen() {
+            Cell::new(arbitrary(g))
+        } else {
+            Cell::new_empty()
+        }
+    }
+
+    fn arbitrary_from(u: &mut Unstructured) -> Cell<T> {
+        if u.int_in_range(0, 1) == 1 {
+            Cell::new(arbitrary_from(u))
         } else {
             Cell::new_empty()
         }
@@ -196,13 +574,141 @@ impl <T: Arbitrary> Arbitrary for Cell<T> {
 }
 
 impl<K: Eq + Hash + Arbitrary> Arbitrary for HashSet<K> {
-    fn arbitrary(sz: uint) -> HashSet<K> {
-        arbiter::<K>(sz).collect()
+    fn arbitrary(g: &mut Gen) -> HashSet<K> {
+        arbiter::<K>(g).collect()
+    }
+
+    fn arbitrary_from(u: &mut Unstructured) -> HashSet<K> {
+        vec_from::<K>(u).move_iter().collect()
     }
 }
 
 impl<K: Eq + Hash + Arbitrary, V: Arbitrary> Arbitrary for HashMap<K, V> {
-    fn arbitrary(sz: uint) -> HashMap<K, V> {
-        arbiter::<(K, V)>(sz).collect()
+    fn arbitrary(g: &mut Gen) -> HashMap<K, V> {
+        arbiter::<(K, V)>(g).collect()
+    }
+
+    fn arbitrary_from(u: &mut Unstructured) -> HashMap<K, V> {
+        vec_from::<(K, V)>(u).move_iter().collect()
+    }
+}
+
+#[test]
+fn test_int_shrink_excludes_self_at_min() {
+    let x: i8 = std::i8::min_value();
+    let mut shrinks = x.shrink();
+    loop {
+        match shrinks.next() {
+            None => break,
+            Some(s) => assert!(s != x),
+        }
+    }
+}
+
+#[test]
+fn test_vec_shrink_reaches_empty() {
+    let v: ~[int] = ~[42];
+    let mut shrinks = v.shrink();
+    let mut saw_empty = false;
+    loop {
+        match shrinks.next() {
+            None => break,
+            Some(s) => {
+                assert!(s != v);
+                if s.len() == 0 { saw_empty = true; }
+            }
+        }
+    }
+    assert!(saw_empty);
+}
+
+#[test]
+fn test_char_arbitrary_never_surrogate() {
+    let mut g = Gen::new(99, 50);
+    let mut i = 0;
+    while i < 2000 {
+        let c: char = arbitrary(&mut g);
+        let n = c as u32;
+        assert!(n < 0xD800 || n > 0xDFFF);
+        i += 1;
+    }
+}
+
+#[test]
+fn test_char_arbitrary_from_never_surrogate() {
+    let mut i = 0u8;
+    loop {
+        let bytes = [i, i, i, i];
+        let mut u = Unstructured::new(bytes);
+        let c: char = arbitrary_from(&mut u);
+        let n = c as u32;
+        assert!(n < 0xD800 || n > 0xDFFF);
+        if i == 255 { break; }
+        i += 1;
+    }
+}
+
+#[test]
+fn test_char_shrink_excludes_self() {
+    let cs = ['a', ' ', '0', 'Z', 'A', '�'];
+    for &c in cs.iter() {
+        let mut shrinks = c.shrink();
+        loop {
+            match shrinks.next() {
+                None => break,
+                Some(s) => assert!(s != c),
+            }
+        }
+    }
+}
+
+/* Excluding self isn't enough to guarantee termination: if a candidate can
+ * be *larger* than the value it came from, two values can shrink into each
+ * other and loop forever. Every candidate must be strictly smaller. */
+#[test]
+fn test_char_shrink_is_strictly_decreasing() {
+    let cs = ['a', ' ', '0', 'Z', 'A', '�'];
+    for &c in cs.iter() {
+        let mut shrinks = c.shrink();
+        loop {
+            match shrinks.next() {
+                None => break,
+                Some(s) => assert!((s as u32) < (c as u32)),
+            }
+        }
+    }
+}
+
+#[test]
+fn test_str_shrink_is_strictly_decreasing() {
+    let ss = [~"hello", ~" a0", ~"�Z"];
+    for s in ss.iter() {
+        let orig: ~[char] = s.iter().collect();
+        let mut shrinks = s.shrink();
+        loop {
+            match shrinks.next() {
+                None => break,
+                Some(cand) => {
+                    let cs: ~[char] = cand.iter().collect();
+                    if cs.len() != orig.len() {
+                        assert!(cs.len() < orig.len());
+                    } else {
+                        /* Same-length candidates come from replacing a single
+                         * element with one of its shrinks, so exactly one
+                         * position differs and it must be strictly smaller. */
+                        let mut i = 0;
+                        let mut diffs = 0;
+                        while i < cs.len() {
+                            if cs[i] != orig[i] {
+                                diffs += 1;
+                                assert!((cs[i] as u32) < (orig[i] as u32));
+                            }
+                            i += 1;
+                        }
+                        assert_eq!(diffs, 1);
+                    }
+                }
+            }
+        }
     }
 }
@@ -0,0 +1,88 @@
+// vim: sts=4 sw=4 et
+
+use super::std;
+use super::arbitrary::{Arbitrary, arbitrary};
+use super::gen::Gen;
+
+/**
+ The property-test driver: run `prop` against `tests` arbitrary values of
+ `A`, drawn from a `Gen` sized `size` and seeded at random.
+
+ On failure, the failing value is minimized by repeatedly taking a
+ still-failing candidate from `shrink()` until no candidate fails, the seed
+ and minimal counterexample are printed, and the minimal value is returned
+ so the failure can be inspected. Pass the printed seed to `check_with_seed`
+ to replay the exact same run.
+ */
+pub fn check<A: Arbitrary + Clone + ToStr>(tests: uint, size: uint, prop: &fn(&A) -> bool) -> Option<A> {
+    let seed: u64 = std::rand::random();
+    check_with_seed(seed, tests, size, prop)
+}
+
+/// Like `check`, but from an explicit seed so a prior failing run can be
+/// reproduced exactly.
+pub fn check_with_seed<A: Arbitrary + Clone + ToStr>(seed: u64, tests: uint, size: uint,
+                                                      prop: &fn(&A) -> bool) -> Option<A> {
+    let mut g = Gen::new(seed, size);
+    let mut i = 0;
+    while i < tests {
+        let x: A = arbitrary(&mut g);
+        if !(*prop)(&x) {
+            let minimal = shrink_failing(x, prop);
+            println!("Falsifiable, after {} tests, seed = {}: {}", i, seed, minimal.to_str());
+            return Some(minimal);
+        }
+        i += 1;
+    }
+    None
+}
+
+/* A well-behaved shrinker only ever emits strictly smaller candidates, so
+ * this loop terminates on its own. This bound is a defense against a buggy
+ * `shrink` (e.g. one that cycles back to a value it already produced)
+ * turning a failing run into a hang instead of a reported counterexample. */
+static MAX_SHRINK_STEPS: uint = 10000;
+
+/// Starting from a value known to fail `prop`, repeatedly replace it with
+/// the first shrink candidate that still fails, until none does.
+fn shrink_failing<A: Arbitrary + Clone>(start: A, prop: &fn(&A) -> bool) -> A {
+    let mut x = start;
+    let mut steps = 0;
+    while steps < MAX_SHRINK_STEPS {
+        let mut candidates = x.shrink();
+        let mut next = None;
+        loop {
+            match candidates.next() {
+                None => break,
+                Some(cand) => {
+                    if !(*prop)(&cand) {
+                        next = Some(cand);
+                        break;
+                    }
+                }
+            }
+        }
+        match next {
+            None => return x,
+            Some(cand) => x = cand,
+        }
+        steps += 1;
+    }
+    x
+}
+
+#[test]
+fn test_shrink_failing_finds_local_minimum() {
+    let x: ~[int] = ~[1, 2, 3, 4, 5];
+    let prop: &fn(&~[int]) -> bool = |v| v.len() <= 2;
+    let minimal = shrink_failing(x, prop);
+
+    assert!(!(*prop)(&minimal));
+    let mut candidates = minimal.shrink();
+    loop {
+        match candidates.next() {
+            None => break,
+            Some(cand) => assert!((*prop)(&cand)),
+        }
+    }
+}
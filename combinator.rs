@@ -0,0 +1,82 @@
+// vim: sts=4 sw=4 et
+
+use super::gen::Gen;
+
+/**
+ Generator combinators built on top of `Gen`.
+
+ The `Arbitrary` impls in this crate always use a type's canonical
+ distribution; these let you write bespoke generators -- "an integer in
+ 0..100", "this variant 90% of the time" -- by composing weighted choices,
+ which is most of what a hand-written `arbitrary` for a recursive enum or
+ tree needs.
+ */
+
+/// Pick uniformly from `xs`. Fails if `xs` is empty.
+pub fn choose<T: Clone>(g: &mut Gen, xs: &[T]) -> T {
+    assert!(xs.len() > 0, "choose: called with no candidates");
+    let i = g.gen_range(0u, xs.len());
+    xs[i].clone()
+}
+
+/// Pick uniformly among a set of generators.
+pub fn one_of<T>(g: &mut Gen, gens: &[~fn(&mut Gen) -> T]) -> T {
+    assert!(gens.len() > 0, "one_of: called with no generators");
+    let i = g.gen_range(0u, gens.len());
+    (gens[i])(g)
+}
+
+/// Pick a generator, weighted by the integer attached to it. Runs the
+/// chosen generator at half the current size, so nested calls reach size 0
+/// after a few levels. Branches are still chosen by weight alone at any
+/// size, so a recursive generator (e.g. for trees) bottoms out only if it
+/// stops picking recursive branches once `g.size()` reaches 0.
+pub fn frequency<T>(g: &mut Gen, choices: &[(uint, ~fn(&mut Gen) -> T)]) -> T {
+    let total = choices.iter().fold(0u, |acc, &(w, _)| acc + w);
+    assert!(total > 0, "frequency: weights summed to zero");
+    let mut n = g.gen_range(0u, total);
+    for &(w, ref f) in choices.iter() {
+        if n < w {
+            let size = g.size();
+            return resize(g, size / 2, |g2| (*f)(g2));
+        }
+        n -= w;
+    }
+    fail!("frequency: weights summed incorrectly")
+}
+
+/// Run `f` with `g` temporarily resized to `n`.
+pub fn resize<T>(g: &mut Gen, n: uint, f: &fn(&mut Gen) -> T) -> T {
+    let old = g.size();
+    g.set_size(n);
+    let result = f(g);
+    g.set_size(old);
+    result
+}
+
+#[test]
+fn test_resize_restores_original_size() {
+    let mut g = Gen::new(7, 50);
+    let inner_size = resize(&mut g, 5, |g2| g2.size());
+    assert_eq!(inner_size, 5);
+    assert_eq!(g.size(), 50);
+}
+
+#[test]
+fn test_frequency_respects_weights() {
+    let mut g = Gen::new(7, 10);
+    let choices: ~[(uint, ~fn(&mut Gen) -> char)] = ~[
+        (1, |_: &mut Gen| 'a'),
+        (99, |_: &mut Gen| 'b'),
+    ];
+    let mut b_count = 0;
+    let mut i = 0;
+    while i < 2000 {
+        if frequency(&mut g, choices) == 'b' {
+            b_count += 1;
+        }
+        i += 1;
+    }
+    // With a 99:1 weighting, 'b' should dominate by a wide margin.
+    assert!(b_count > 1800);
+}
@@ -0,0 +1,56 @@
+// vim: sts=4 sw=4 et
+
+use super::std;
+use super::std::rand::{Rand, Rng, IsaacRng, SeedableRng};
+
+/**
+ A `Gen` bundles a source of randomness together with a size factor, and is
+ threaded through every `arbitrary` call.
+
+ Building a `Gen` from an explicit seed (see `Gen::new`) means a whole test
+ run -- including any failure -- can be reproduced exactly by feeding the
+ same seed back in. See `check::check` and `check::check_with_seed` for the
+ driver that picks the seed, prints it on failure, and accepts it back in to
+ replay a run.
+ */
+pub struct Gen {
+    priv rng: IsaacRng,
+    priv size: uint,
+}
+
+impl Gen {
+    /// Create a `Gen` seeded from `seed`, generating values scaled by `size`.
+    pub fn new(seed: u64, size: uint) -> Gen {
+        let seed_arr = [(seed >> 32) as u32, seed as u32];
+        Gen { rng: SeedableRng::from_seed(seed_arr.as_slice()), size: size }
+    }
+
+    /// The size factor `arbitrary` impls should scale generated values by.
+    pub fn size(&self) -> uint { self.size }
+
+    /// Override the size factor, e.g. to run a sub-generator at a fixed size.
+    pub fn set_size(&mut self, size: uint) { self.size = size }
+
+    /// Generate a value using its `Rand` impl.
+    pub fn gen<T: Rand>(&mut self) -> T {
+        self.rng.gen()
+    }
+
+    /// Generate a value in the half-open range `[lo, hi)`.
+    pub fn gen_range<T: Rand + Ord + Num>(&mut self, lo: T, hi: T) -> T {
+        self.rng.gen_integer_range(lo, hi)
+    }
+}
+
+#[test]
+fn test_same_seed_is_reproducible() {
+    let mut g1 = Gen::new(42, 10);
+    let mut g2 = Gen::new(42, 10);
+    let mut i = 0;
+    while i < 20 {
+        let a: u32 = g1.gen();
+        let b: u32 = g2.gen();
+        assert_eq!(a, b);
+        i += 1;
+    }
+}
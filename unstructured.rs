@@ -0,0 +1,84 @@
+// vim: sts=4 sw=4 et
+
+/**
+ `Unstructured` wraps a finite byte buffer with a read cursor, so that
+ fuzzer-supplied bytes (AFL, libFuzzer, ...) can be turned into structured
+ values instead of only a PRNG stream.
+
+ Every read is total: once the buffer is exhausted, further reads yield
+ zero/default bytes rather than failing, so *any* byte string maps to some
+ valid value and the fuzzer's byte-level mutations turn into structured
+ mutations of that value.
+ */
+pub struct Unstructured<'a> {
+    priv data: &'a [u8],
+    priv pos: uint,
+}
+
+impl<'a> Unstructured<'a> {
+    /// Wrap `data` for reading from the start.
+    pub fn new(data: &'a [u8]) -> Unstructured<'a> {
+        Unstructured { data: data, pos: 0 }
+    }
+
+    /// Fill `buf` with the next `buf.len()` bytes, padding with zero once
+    /// the underlying buffer is exhausted.
+    pub fn fill(&mut self, buf: &mut [u8]) {
+        let mut i = 0;
+        while i < buf.len() {
+            buf[i] = if self.pos < self.data.len() {
+                let b = self.data[self.pos];
+                self.pos += 1;
+                b
+            } else {
+                0
+            };
+            i += 1;
+        }
+    }
+
+    /// Consume a value in the inclusive range `[lo, hi]`.
+    ///
+    /// Reads as many bytes as `hi - lo` needs, so a span over 256 isn't
+    /// silently clamped to the first byte's worth of values.
+    pub fn int_in_range(&mut self, lo: uint, hi: uint) -> uint {
+        if lo >= hi {
+            return lo;
+        }
+        let span = hi - lo + 1;
+        let mut nbytes = 1;
+        while (1u << (8 * nbytes)) < span && nbytes < 8 {
+            nbytes += 1;
+        }
+        let mut buf = [0u8, ..8];
+        self.fill(buf.mut_slice_to(nbytes));
+        let mut x: uint = 0;
+        let mut i = 0;
+        while i < nbytes {
+            x = (x << 8) | (buf[i] as uint);
+            i += 1;
+        }
+        lo + x % span
+    }
+}
+
+#[test]
+fn test_fill_pads_with_zero_past_end() {
+    let mut u = Unstructured::new([1u8, 2u8]);
+    let mut buf = [9u8, 9u8, 9u8, 9u8];
+    u.fill(buf);
+    assert_eq!(buf, [1u8, 2u8, 0u8, 0u8]);
+}
+
+#[test]
+fn test_int_in_range_past_end_is_zero() {
+    let mut u = Unstructured::new([]);
+    assert_eq!(u.int_in_range(10, 20), 10);
+}
+
+#[test]
+fn test_int_in_range_covers_large_span() {
+    // A span over 256 must consume more than one byte: 0xFFFFFF % 1000001.
+    let mut u = Unstructured::new([0xFFu8, 0xFFu8, 0xFFu8]);
+    assert_eq!(u.int_in_range(0, 1000000), 777199);
+}